@@ -11,5 +11,8 @@ pub mod tracing;
 #[cfg(feature = "telegram")]
 pub mod telegram;
 
+#[cfg(feature = "measure")]
+pub mod measure;
+
 #[cfg(all(feature = "tts", target_os = "windows"))]
 pub mod tts;
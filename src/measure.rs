@@ -1,4 +1,9 @@
-use std::io::Read;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
 
 /// Smoothing factor
@@ -119,3 +124,294 @@ impl<R: Read> Read for MeasuringReader<R> {
         Ok(read)
     }
 }
+
+/// Target latency the adaptive read-ahead window is sized for: the window is the observed
+/// [`Average`] speed times this duration, clamped to [`MIN_PREFETCH`]..=[`MAX_PREFETCH`]
+const DEFAULT_TARGET_LATENCY: Duration = Duration::from_secs(2);
+
+/// Lower bound for the adaptive prefetch window
+const MIN_PREFETCH: usize = 64 * 1024;
+
+/// Upper bound for the adaptive prefetch window, and the hard cap on how many bytes the
+/// background thread is allowed to keep resident in `Shared.buffer` at once
+const MAX_PREFETCH: usize = 64 * 1024 * 1024;
+
+/// Chunk size used by the background read-ahead thread
+const READ_CHUNK: usize = 64 * 1024;
+
+struct Shared {
+    /// Bytes fetched but not yet consumed, starting at `base`. Capped at [`MAX_PREFETCH`]
+    /// bytes; the background thread blocks rather than growing it further.
+    buffer: VecDeque<u8>,
+    /// Stream offset of `buffer`'s first byte, i.e. how much has been consumed so far
+    base: usize,
+    /// Stream offset the background thread should fetch up to
+    target: usize,
+    avg: f64,
+    buf: Buffer,
+    eof: bool,
+    error: Option<io::Error>,
+    /// Set once the background thread has permanently stopped (EOF, error, or requested to
+    /// stop), even after `error` has been taken by a caller. Lets `read` and `fetch_blocking`
+    /// notice the thread is gone instead of waiting on a condvar nobody will ever notify again.
+    done: bool,
+    max_rate: Option<f64>,
+    rate_start: Instant,
+}
+
+/// Reader that fills a bounded ring buffer from `inner` on a background thread
+///
+/// Unlike [`MeasuringReader`], `read()` is served from memory while I/O happens ahead
+/// of consumption. The prefetch window adapts to the observed transfer speed (see
+/// [`avg`](Self::avg)), and an optional [`with_max_rate`](Self::with_max_rate)
+/// throttle caps throughput for well-behaved background downloads. Residency is bounded:
+/// the background thread never keeps more than [`MAX_PREFETCH`] bytes buffered ahead of
+/// the consumer, regardless of how far [`fetch`](Self::fetch) asks it to look ahead.
+pub struct PrefetchReader {
+    shared: Arc<(Mutex<Shared>, Condvar)>,
+    size_hint: Option<usize>,
+    target_latency: Duration,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PrefetchReader {
+    pub fn new<R: Read + Send + 'static>(inner: R) -> Self {
+        Self::build(inner, None)
+    }
+
+    pub fn with_size_hint<R: Read + Send + 'static>(inner: R, size_hint: usize) -> Self {
+        Self::build(inner, Some(size_hint))
+    }
+
+    fn build<R: Read + Send + 'static>(inner: R, size_hint: Option<usize>) -> Self {
+        let shared = Arc::new((
+            Mutex::new(Shared {
+                buffer: VecDeque::new(),
+                base: 0,
+                target: 0,
+                avg: 0.0,
+                buf: Buffer::new(),
+                eof: false,
+                error: None,
+                done: false,
+                max_rate: None,
+                rate_start: Instant::now(),
+            }),
+            Condvar::new(),
+        ));
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = std::thread::spawn({
+            let shared = Arc::clone(&shared);
+            let stop = Arc::clone(&stop);
+            move || read_ahead(inner, shared, stop)
+        });
+
+        Self {
+            shared,
+            size_hint,
+            target_latency: DEFAULT_TARGET_LATENCY,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Cap throughput to `bytes_per_sec`, throttling the background read-ahead thread
+    pub fn with_max_rate(self, bytes_per_sec: f64) -> Self {
+        self.shared.0.lock().expect("lock poisoned").max_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Request that `range` be resident in the buffer, without waiting for it
+    ///
+    /// Residency is bounded by [`MAX_PREFETCH`]: the background thread will only ever
+    /// fetch that many bytes ahead of what has already been consumed, so asking for a
+    /// far-away `range` just keeps it busy until the consumer catches up rather than
+    /// buffering the whole range at once.
+    pub fn fetch(&self, range: Range<usize>) {
+        let (lock, cvar) = &*self.shared;
+        let mut shared = lock.lock().expect("lock poisoned");
+        shared.target = shared.target.max(range.end);
+        cvar.notify_all();
+    }
+
+    /// Request that `range` be resident in the buffer, blocking until it is (or EOF/error)
+    ///
+    /// Residency is capped at [`MAX_PREFETCH`] bytes ahead of what has already been
+    /// consumed, so a `range` wider than that only blocks until the cap is reached, not
+    /// until the whole range is resident. Call [`read`](Self::read) to consume bytes and
+    /// free up residency for the rest of the range.
+    pub fn fetch_blocking(&self, range: Range<usize>) -> io::Result<()> {
+        self.fetch(range.clone());
+
+        let (lock, cvar) = &*self.shared;
+        let mut shared = lock.lock().expect("lock poisoned");
+        while shared.base + shared.buffer.len() < range.end
+            && shared.buffer.len() < MAX_PREFETCH
+            && !shared.done
+        {
+            shared = cvar.wait(shared).expect("lock poisoned");
+        }
+
+        match shared.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.shared.0.lock().expect("lock poisoned").base
+    }
+
+    pub fn avg(&self) -> Average {
+        Average(self.shared.0.lock().expect("lock poisoned").avg)
+    }
+
+    /// Time remaining
+    ///
+    /// Returns `None` if `size_hint` was not set or a remaining time could not be computed.
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let size_hint = self.size_hint?;
+        let shared = self.shared.0.lock().expect("lock poisoned");
+        Duration::try_from_secs_f64((size_hint - shared.base) as f64 / shared.avg).ok()
+    }
+
+    pub fn percentage(&self) -> Option<f64> {
+        let size_hint = self.size_hint?;
+        let total = self.total();
+        Some((total as f64 / size_hint as f64) * 100.0)
+    }
+}
+
+impl Read for PrefetchReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (lock, cvar) = &*self.shared;
+        let mut shared = lock.lock().expect("lock poisoned");
+
+        while shared.buffer.is_empty() && !shared.done {
+            // Size the read-ahead window from the observed speed so slow links prefetch
+            // less and fast links keep the pipe full
+            let window = (shared.avg * self.target_latency.as_secs_f64()) as usize;
+            let window = window.clamp(MIN_PREFETCH, MAX_PREFETCH);
+            shared.target = shared.target.max(shared.base + window);
+            cvar.notify_all();
+            shared = cvar.wait(shared).expect("lock poisoned");
+        }
+
+        if shared.buffer.is_empty() {
+            // `shared.error` may already have been taken by a prior `fetch_blocking` call;
+            // a dead producer with nothing left to report is just EOF
+            return match shared.error.take() {
+                Some(err) => Err(err),
+                None => Ok(0),
+            };
+        }
+
+        let n = shared.buffer.len().min(buf.len());
+        for (slot, byte) in buf[..n].iter_mut().zip(shared.buffer.drain(..n)) {
+            *slot = byte;
+        }
+        shared.base += n;
+        drop(shared);
+
+        // Draining frees up residency under `MAX_PREFETCH`; wake the background thread in
+        // case it was blocked on the buffer being full
+        cvar.notify_all();
+
+        Ok(n)
+    }
+}
+
+impl Drop for PrefetchReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.shared.1.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Background loop that fetches from `inner` ahead of consumption, up to `shared.target`
+fn read_ahead<R: Read>(mut inner: R, shared: Arc<(Mutex<Shared>, Condvar)>, stop: Arc<AtomicBool>) {
+    let (lock, cvar) = &*shared;
+    let mut chunk = vec![0u8; READ_CHUNK];
+
+    loop {
+        let mut guard = lock.lock().expect("lock poisoned");
+        // Park whenever there's nothing to do: no demand ahead of `target`, or the buffer
+        // is already at its residency cap. `read`/`fetch`/`fetch_blocking` notify the
+        // condvar once there's more target or more room to produce into.
+        while !stop.load(Ordering::Relaxed)
+            && !guard.eof
+            && guard.error.is_none()
+            && (guard.buffer.len() >= MAX_PREFETCH || guard.base + guard.buffer.len() >= guard.target)
+        {
+            guard = cvar.wait(guard).expect("lock poisoned");
+        }
+
+        if stop.load(Ordering::Relaxed) {
+            guard.done = true;
+            return;
+        }
+        if guard.eof || guard.error.is_some() {
+            return;
+        }
+
+        // Guaranteed positive: the wait loop only exits once there's both residency
+        // headroom and unmet demand
+        let want = (guard.target - (guard.base + guard.buffer.len()))
+            .min(chunk.len())
+            .min(MAX_PREFETCH - guard.buffer.len());
+
+        let max_rate = guard.max_rate;
+        let rate_start = guard.rate_start;
+        let produced_before = guard.base + guard.buffer.len();
+        drop(guard);
+
+        match inner.read(&mut chunk[..want]) {
+            Ok(0) => {
+                let mut guard = lock.lock().expect("lock poisoned");
+                guard.eof = true;
+                guard.done = true;
+                cvar.notify_all();
+                return;
+            }
+            Ok(n) => {
+                let mut guard = lock.lock().expect("lock poisoned");
+                guard.buffer.extend(&chunk[..n]);
+
+                // Update the EMA the same way `MeasuringReader` does
+                let elapsed = guard.buf.time.elapsed();
+                guard.buf.read += n;
+                if elapsed >= UPDATE_RATE {
+                    let speed = guard.buf.read as f64 / elapsed.as_secs_f64();
+                    guard.avg = ALPHA * speed + (1.0 - ALPHA) * guard.avg;
+                    guard.buf = Buffer::new();
+                }
+                cvar.notify_all();
+                drop(guard);
+
+                // Token-bucket throttle: sleep just enough to cap throughput at `max_rate`
+                if let Some(max_rate) = max_rate {
+                    if max_rate > 0.0 {
+                        let produced = (produced_before + n) as f64;
+                        let allowed = max_rate * rate_start.elapsed().as_secs_f64();
+                        if produced > allowed {
+                            std::thread::sleep(Duration::from_secs_f64(
+                                (produced - allowed) / max_rate,
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(err) => {
+                let mut guard = lock.lock().expect("lock poisoned");
+                guard.error = Some(err);
+                guard.done = true;
+                cvar.notify_all();
+                return;
+            }
+        }
+    }
+}
@@ -35,7 +35,13 @@
 //!     ControlFlow::Continue
 //! }
 //! ```
-use std::io::Write;
+//!
+//! [`repl`] is a thin wrapper around [`repl_with`] bound to stdin/stdout. Use
+//! [`repl_with`] directly to drive the loop over any [`BufRead`]/[`Write`] pair, or
+//! [`serve`] to expose the same command enum as a TCP admin console.
+use std::io::{BufRead, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
 
 /// Types needed for the REPL definition
 pub mod prelude {
@@ -49,22 +55,54 @@ pub enum ControlFlow {
     Exit,
 }
 
-/// Run the REPL
+/// Run the REPL over stdin/stdout
 ///
 /// See top level documentation for an example
 pub fn repl<P, F>(mut evaluate: F)
 where
     P: clap::Parser,
     F: FnMut(&mut P) -> ControlFlow,
+{
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+
+    repl_with(stdin.lock(), stdout.lock(), |parser, _output| {
+        evaluate(parser)
+    });
+}
+
+/// Run the REPL over an arbitrary reader and writer
+///
+/// Unlike [`repl`], `evaluate` is also handed the output stream, so it can write its
+/// response back to `output` instead of going through global stdout. This is what
+/// makes the loop usable over a socket, see [`serve`].
+pub fn repl_with<P, F, R, W>(mut input: R, mut output: W, mut evaluate: F)
+where
+    P: clap::Parser,
+    F: FnMut(&mut P, &mut W) -> ControlFlow,
+    R: BufRead,
+    W: Write,
 {
     let mut control_flow = ControlFlow::Continue;
     let mut buf = String::new();
     let mut parser: Option<P> = None;
 
     while !matches!(control_flow, ControlFlow::Exit) {
-        let Some(words) = read(&mut buf) else {
-            println!("error: malformed input");
-            continue;
+        let line = match read(&mut input, &mut output, &mut buf) {
+            Ok(line) => line,
+            Err(err) => {
+                let _ = writeln!(output, "error: {err}");
+                break;
+            }
+        };
+
+        let words = match line {
+            Line::Eof => break,
+            Line::Malformed => {
+                let _ = writeln!(output, "error: malformed input");
+                continue;
+            }
+            Line::Words(words) => words,
         };
 
         if words.is_empty() {
@@ -74,23 +112,69 @@ where
         let parser = match P::try_parse_from(words) {
             Ok(p) => parser.insert(p),
             Err(err) => {
-                println!("{err}");
+                let _ = writeln!(output, "{err}");
                 continue;
             }
         };
 
-        control_flow = evaluate(parser);
+        control_flow = evaluate(parser, &mut output);
+    }
+}
+
+/// Serve the REPL over TCP, running one session per connection
+///
+/// Frames are newline-delimited, same as an interactive line: each connection gets
+/// its own [`repl_with`] loop, reading and writing against that connection's socket.
+/// `make_evaluate` is called once per connection to build a fresh `evaluate` closure.
+pub fn serve<P, F>(
+    addr: impl ToSocketAddrs,
+    make_evaluate: impl Fn() -> F + Send + Sync + 'static,
+) -> std::io::Result<()>
+where
+    P: clap::Parser + Send + 'static,
+    F: FnMut(&mut P, &mut TcpStream) -> ControlFlow + Send + 'static,
+{
+    let listener = TcpListener::bind(addr)?;
+    let make_evaluate = Arc::new(make_evaluate);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let make_evaluate = Arc::clone(&make_evaluate);
+
+        std::thread::spawn(move || {
+            let mut evaluate = make_evaluate();
+            let reader = std::io::BufReader::new(
+                stream.try_clone().expect("failed to clone connection"),
+            );
+
+            repl_with::<P, _, _, _>(reader, stream, |parser, output| evaluate(parser, output));
+        });
     }
+
+    Ok(())
 }
 
-fn read(buf: &mut String) -> Option<Vec<String>> {
-    print!("> ");
-    std::io::stdout().flush().expect("failed to flush stdout");
+enum Line {
+    Eof,
+    Malformed,
+    Words(Vec<String>),
+}
+
+fn read<R: BufRead, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    buf: &mut String,
+) -> std::io::Result<Line> {
+    write!(output, "> ")?;
+    output.flush()?;
 
     buf.clear();
-    std::io::stdin()
-        .read_line(buf)
-        .expect("failed to read line from stdin");
+    if input.read_line(buf)? == 0 {
+        return Ok(Line::Eof);
+    }
 
-    shlex::split(buf.trim())
+    Ok(match shlex::split(buf.trim()) {
+        Some(words) => Line::Words(words),
+        None => Line::Malformed,
+    })
 }
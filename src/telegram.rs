@@ -8,11 +8,107 @@
 //! telegram.send_message("Hello, World!").unwrap();
 //! ```
 
-const HTTP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+use std::thread;
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Telegram's hard limit on the number of characters in a single message
+const MESSAGE_LIMIT: usize = 4096;
+
+/// Maximum number of retries after a `429 Too Many Requests` response
+const MAX_RETRIES: u32 = 3;
+
+/// Formatting applied to a message's text, see Telegram's `parse_mode`
+#[derive(Copy, Clone, Debug)]
+pub enum ParseMode {
+    MarkdownV2,
+    Html,
+}
+
+impl ParseMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            ParseMode::MarkdownV2 => "MarkdownV2",
+            ParseMode::Html => "HTML",
+        }
+    }
+}
+
+/// Options for [`Telegram::send_message_with`]
+#[derive(Default)]
+#[must_use]
+pub struct SendOptions {
+    parse_mode: Option<ParseMode>,
+    disable_notification: bool,
+}
+
+impl SendOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the message text using the given [`ParseMode`]
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    /// Deliver the message without a notification sound
+    pub fn with_silent(mut self) -> Self {
+        self.disable_notification = true;
+        self
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct ErrorResponse {
+    parameters: Option<ErrorParameters>,
+}
+
+#[derive(serde::Deserialize)]
+struct ErrorParameters {
+    retry_after: u64,
+}
+
+/// Error returned by [`Telegram`]'s send methods
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    /// Every retry was exhausted while Telegram kept responding `429 Too Many Requests`
+    RateLimited { retry_after: Duration },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Http(err) => write!(f, "{err}"),
+            Error::RateLimited { retry_after } => {
+                write!(f, "rate limited by telegram, retry after {retry_after:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(err) => Some(err),
+            Error::RateLimited { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        Error::Http(err)
+    }
+}
 
 pub struct Telegram {
     client: reqwest::blocking::Client,
-    url_base: String,
+    base_url: String,
+    chat_id: String,
 }
 
 impl Telegram {
@@ -28,18 +124,166 @@ impl Telegram {
 
     /// Create a new `Telegram` instance with an already existing agent
     pub fn with_agent(token: &str, chat_id: &str, client: reqwest::blocking::Client) -> Self {
-        let url_base =
-            format!("https://api.telegram.org/bot{token}/sendMessage?chat_id={chat_id}&text=");
-
-        Self { client, url_base }
+        Self {
+            client,
+            base_url: format!("https://api.telegram.org/bot{token}"),
+            chat_id: chat_id.to_string(),
+        }
     }
 
     /// Send a telegram message
-    pub fn send_message(&self, text: &str) -> reqwest::Result<()> {
-        // URL encode the message to allow for special characters
-        let encoded_msg = urlencoding::encode(text);
-        let url = format!("{}{}", self.url_base, encoded_msg);
-        self.client.post(url).send()?;
+    ///
+    /// Messages longer than Telegram's 4096 character limit are automatically split into
+    /// sequential sends. See [`send_message_with`](Self::send_message_with) for `parse_mode`
+    /// and silent delivery.
+    pub fn send_message(&self, text: &str) -> Result<(), Error> {
+        self.send_message_with(text, &SendOptions::default())
+    }
+
+    /// Send a telegram message with [`SendOptions`]
+    pub fn send_message_with(&self, text: &str, options: &SendOptions) -> Result<(), Error> {
+        for chunk in split_message(text) {
+            let mut form = form_urlencoded::Serializer::new(String::new());
+            form.append_pair("chat_id", &self.chat_id)
+                .append_pair("text", chunk);
+            if let Some(parse_mode) = options.parse_mode {
+                form.append_pair("parse_mode", parse_mode.as_str());
+            }
+            if options.disable_notification {
+                form.append_pair("disable_notification", "true");
+            }
+
+            self.post_form("sendMessage", form.finish())?;
+        }
+
         Ok(())
     }
+
+    /// Send a document, e.g. a log file, as an attachment
+    pub fn send_document(&self, bytes: Vec<u8>, file_name: &str) -> Result<(), Error> {
+        self.send_media("sendDocument", "document", bytes, file_name)
+    }
+
+    /// Send an audio file, e.g. a [`tts::Spoken`](crate::tts::Spoken) wav, as an attachment
+    pub fn send_audio(&self, bytes: Vec<u8>, file_name: &str) -> Result<(), Error> {
+        self.send_media("sendAudio", "audio", bytes, file_name)
+    }
+
+    /// Send a voice message, e.g. a [`tts::Spoken`](crate::tts::Spoken) wav, as an attachment
+    pub fn send_voice(&self, bytes: Vec<u8>, file_name: &str) -> Result<(), Error> {
+        self.send_media("sendVoice", "voice", bytes, file_name)
+    }
+
+    fn send_media(
+        &self,
+        method: &str,
+        field: &'static str,
+        bytes: Vec<u8>,
+        file_name: &str,
+    ) -> Result<(), Error> {
+        let chat_id = self.chat_id.clone();
+        let field = field.to_string();
+        let file_name = file_name.to_string();
+
+        self.post_multipart(method, move || {
+            let part = reqwest::blocking::multipart::Part::bytes(bytes.clone())
+                .file_name(file_name.clone());
+            reqwest::blocking::multipart::Form::new()
+                .text("chat_id", chat_id.clone())
+                .part(field.clone(), part)
+        })
+    }
+
+    /// POST a `application/x-www-form-urlencoded` body, retrying on `429 Too Many Requests`
+    ///
+    /// Returns [`Error::RateLimited`] if Telegram is still responding `429` once retries are
+    /// exhausted, rather than silently dropping the message.
+    fn post_form(&self, method: &str, body: String) -> Result<(), Error> {
+        let url = format!("{}/{method}", self.base_url);
+
+        for attempt in 0..=MAX_RETRIES {
+            let response = self
+                .client
+                .post(&url)
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    "application/x-www-form-urlencoded",
+                )
+                .body(body.clone())
+                .send()?;
+
+            match retry_after(response)? {
+                Some(retry_after) if attempt < MAX_RETRIES => thread::sleep(retry_after),
+                Some(retry_after) => return Err(Error::RateLimited { retry_after }),
+                None => return Ok(()),
+            }
+        }
+
+        unreachable!("loop always returns on its last iteration")
+    }
+
+    /// POST a `multipart/form-data` body, retrying on `429 Too Many Requests`
+    ///
+    /// `form` is called again for every attempt, since [`Form`](reqwest::blocking::multipart::Form)
+    /// cannot be cloned or reused once sent. Returns [`Error::RateLimited`] if Telegram is still
+    /// responding `429` once retries are exhausted, rather than silently dropping the message.
+    fn post_multipart(
+        &self,
+        method: &str,
+        form: impl Fn() -> reqwest::blocking::multipart::Form,
+    ) -> Result<(), Error> {
+        let url = format!("{}/{method}", self.base_url);
+
+        for attempt in 0..=MAX_RETRIES {
+            let response = self.client.post(&url).multipart(form()).send()?;
+
+            match retry_after(response)? {
+                Some(retry_after) if attempt < MAX_RETRIES => thread::sleep(retry_after),
+                Some(retry_after) => return Err(Error::RateLimited { retry_after }),
+                None => return Ok(()),
+            }
+        }
+
+        unreachable!("loop always returns on its last iteration")
+    }
+}
+
+/// If `response` is a `429 Too Many Requests`, consume it and return Telegram's `retry_after` hint
+fn retry_after(response: reqwest::blocking::Response) -> Result<Option<Duration>, Error> {
+    if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        response.error_for_status()?;
+        return Ok(None);
+    }
+
+    let body: ErrorResponse = response.json()?;
+    let retry_after = body
+        .parameters
+        .map_or(Duration::from_secs(1), |parameters| {
+            Duration::from_secs(parameters.retry_after)
+        });
+
+    Ok(Some(retry_after))
+}
+
+/// Split `text` into chunks that fit Telegram's 4096 character message limit
+fn split_message(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        return vec![text];
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut count = 0;
+
+    for (idx, _) in text.char_indices() {
+        if count == MESSAGE_LIMIT {
+            chunks.push(&text[start..idx]);
+            start = idx;
+            count = 0;
+        }
+        count += 1;
+    }
+    chunks.push(&text[start..]);
+
+    chunks
 }
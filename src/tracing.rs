@@ -12,26 +12,64 @@
 //! utility::tracing::file("log.txt").with_level(tracing::Level::DEBUG).init();
 //! ```
 //!
+//! File logging blocks and flushes on every event by default. For hot paths, log to the
+//! file on a background thread instead
+//! ```
+//! let _defer = utility::tracing::stdout().with_file_nonblocking("log.txt").init();
+//! ```
+//!
 //! Set up tracing for use with tracy
 //! ```
 //! let _defer = utility::tracing::tracy().init();
 //! ```
 
+use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
 use std::time::Duration;
 use tracing_subscriber::layer::SubscriberExt;
 
 /// Amount to time to wait for tracy to establish / finish the connection
 const TRACY_CONNECTION_TIMEOUT: Duration = Duration::from_secs(1);
 
+/// Number of pending log lines the background file writer buffers before `Overflow` applies
+const FILE_CHANNEL_CAPACITY: usize = 1024;
+
+/// How often the background file writer flushes and checks for shutdown
+const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How the background file writer behaves once [`FILE_CHANNEL_CAPACITY`] is exceeded
+#[derive(Copy, Clone, Debug)]
+pub enum Overflow {
+    /// Block the calling thread until the background writer catches up
+    Block,
+    /// Drop the event and increment a lost-line counter instead of blocking
+    DropAndCount,
+}
+
+/// Whether the file layer writes (and flushes) on the calling thread or a background one
+#[derive(Clone, Debug)]
+enum FileMode {
+    Blocking,
+    NonBlocking { overflow: Overflow },
+}
+
+#[derive(Clone, Debug)]
+struct FileConfig {
+    path: PathBuf,
+    mode: FileMode,
+}
+
 /// Configuration to use for tracing
 #[must_use]
 pub struct TracingBuilder {
     log_to_stdout: bool,
     log_to_tracy: bool,
-    log_to_file: Option<PathBuf>,
+    log_to_file: Option<FileConfig>,
     log_level: Option<tracing::Level>,
 }
 
@@ -60,8 +98,44 @@ impl TracingBuilder {
     }
 
     /// Enable logging to the specified file
+    ///
+    /// Every event flushes on the calling thread. Use
+    /// [`with_file_nonblocking`](Self::with_file_nonblocking) to move that I/O off the hot path.
     pub fn with_file(mut self, path: impl AsRef<Path>) -> Self {
-        self.log_to_file = Some(path.as_ref().to_path_buf());
+        self.log_to_file = Some(FileConfig {
+            path: path.as_ref().to_path_buf(),
+            mode: FileMode::Blocking,
+        });
+        self
+    }
+
+    /// Enable logging to the specified file through a dedicated background writer thread
+    ///
+    /// The calling thread only serializes the formatted event and pushes it onto a bounded
+    /// queue; the background thread owns the file, batches writes, and flushes periodically.
+    /// Defaults to [`Overflow::Block`] once the queue is full, see
+    /// [`with_overflow_policy`](Self::with_overflow_policy).
+    pub fn with_file_nonblocking(mut self, path: impl AsRef<Path>) -> Self {
+        self.log_to_file = Some(FileConfig {
+            path: path.as_ref().to_path_buf(),
+            mode: FileMode::NonBlocking {
+                overflow: Overflow::Block,
+            },
+        });
+        self
+    }
+
+    /// Set the queue overflow policy for [`with_file_nonblocking`](Self::with_file_nonblocking)
+    ///
+    /// Has no effect unless non-blocking file logging is enabled.
+    pub fn with_overflow_policy(mut self, overflow: Overflow) -> Self {
+        if let Some(FileConfig {
+            mode: FileMode::NonBlocking { overflow: policy },
+            ..
+        }) = &mut self.log_to_file
+        {
+            *policy = overflow;
+        }
         self
     }
 
@@ -73,7 +147,9 @@ impl TracingBuilder {
 
     /// Initialize the tracing configuration
     ///
-    /// Returns [`TracingDefer`], which can be ignored if the `tracy` feature is not enabled.
+    /// Returns [`TracingDefer`], which must be held until shutdown: dropping it joins the
+    /// background file writer (if any) and flushes its queue, and waits for tracy to
+    /// finish (if enabled).
     pub fn init(self) -> TracingDefer {
         // stdout
         let stdout_layer = self
@@ -81,25 +157,43 @@ impl TracingBuilder {
             .then(|| tracing_subscriber::fmt::layer().with_ansi(false).with_writer(std::io::stdout));
 
         // file
-        let file_layer = self.log_to_file.map(|path| {
-            // Create file and it's writer
-            let log_file = std::fs::File::create(path).expect("failed to create log file");
-            let wtr = Writer(Arc::new(Mutex::new(BufWriter::new(log_file))));
-
-            // Register a panic hook that prints the stack backtrace to the file
-            let panic_wtr = wtr.clone();
-            let old_hook = std::panic::take_hook();
-            std::panic::set_hook(Box::new(move |info| {
-                if let Ok(mut guard) = panic_wtr.0.try_lock() {
-                    let backtrace = std::backtrace::Backtrace::force_capture();
-                    let msg = format!("{info}\n\nStack backtrace:\n{backtrace}");
-                    guard
-                        .write_all(msg.as_bytes())
-                        .expect("failed to write backtrace");
-                    guard.flush().expect("failed to flush buffer");
+        let mut file_worker = None;
+        let mut lost = None;
+        let file_layer = self.log_to_file.map(|FileConfig { path, mode }| {
+            let log_file = std::fs::File::create(&path).expect("failed to create log file");
+
+            let wtr = match mode {
+                FileMode::Blocking => {
+                    let wtr = Writer(Arc::new(Mutex::new(BufWriter::new(log_file))));
+                    install_panic_hook(Arc::clone(&wtr.0));
+                    FileWriter::Blocking(wtr)
                 }
-                old_hook(info);
-            }));
+                FileMode::NonBlocking { overflow } => {
+                    // The panic hook writes directly to its own handle, bypassing the queue,
+                    // so a crashing process still records its stack even if the background
+                    // writer never gets to drain it
+                    let panic_file = log_file.try_clone().expect("failed to clone log file");
+                    install_panic_hook(Arc::new(Mutex::new(panic_file)));
+
+                    let (sender, receiver) = mpsc::sync_channel(FILE_CHANNEL_CAPACITY);
+                    let lost_lines = Arc::new(AtomicU64::new(0));
+                    let stop = Arc::new(AtomicBool::new(false));
+
+                    let handle = std::thread::spawn({
+                        let stop = Arc::clone(&stop);
+                        move || run_background_writer(receiver, BufWriter::new(log_file), stop)
+                    });
+
+                    file_worker = Some(FileWorker { stop, handle });
+                    lost = Some(Arc::clone(&lost_lines));
+
+                    FileWriter::NonBlocking(NonBlockingWriter {
+                        sender,
+                        overflow,
+                        lost: lost_lines,
+                    })
+                }
+            };
 
             tracing_subscriber::fmt::layer()
                 .with_writer(wtr)
@@ -127,7 +221,7 @@ impl TracingBuilder {
         tracing::subscriber::set_global_default(subscriber)
             .expect("failed to set global tracing subscriber");
 
-        TracingDefer::new(self.log_to_tracy)
+        TracingDefer::new(self.log_to_tracy, file_worker, lost)
     }
 }
 
@@ -164,18 +258,96 @@ pub fn file(path: impl AsRef<Path>) -> TracingBuilder {
     TracingBuilder::empty().with_file(path)
 }
 
-/// Utility struct that ensures proper shutdown of tracy when dropped
-#[cfg_attr(feature = "tracy", must_use)]
+/// Register a panic hook that prints the stack backtrace to `target`
+fn install_panic_hook<W: Write + Send + 'static>(target: Arc<Mutex<W>>) {
+    let old_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Ok(mut guard) = target.try_lock() {
+            let backtrace = std::backtrace::Backtrace::force_capture();
+            let msg = format!("{info}\n\nStack backtrace:\n{backtrace}");
+            guard
+                .write_all(msg.as_bytes())
+                .expect("failed to write backtrace");
+            guard.flush().expect("failed to flush buffer");
+        }
+        old_hook(info);
+    }));
+}
+
+/// Background thread backing [`TracingBuilder::with_file_nonblocking`]
+///
+/// Batches whatever is queued up between wakeups into a single write, and flushes the file
+/// on every wakeup, so logging stays periodic even during a quiet stretch.
+fn run_background_writer(
+    receiver: mpsc::Receiver<Vec<u8>>,
+    mut file: BufWriter<File>,
+    stop: Arc<AtomicBool>,
+) {
+    loop {
+        match receiver.recv_timeout(FLUSH_INTERVAL) {
+            Ok(msg) => {
+                let _ = file.write_all(&msg);
+                for msg in receiver.try_iter() {
+                    let _ = file.write_all(&msg);
+                }
+                let _ = file.flush();
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let _ = file.flush();
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    // Drain and flush whatever arrived right before shutdown
+    for msg in receiver.try_iter() {
+        let _ = file.write_all(&msg);
+    }
+    let _ = file.flush();
+}
+
+struct FileWorker {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Utility struct that ensures proper shutdown of tracy and the background file writer when dropped
+#[must_use]
 pub struct TracingDefer {
     is_tracy_enabled: bool,
+    file_worker: Option<FileWorker>,
+    lost: Option<Arc<AtomicU64>>,
 }
 
 impl TracingDefer {
-    fn new(is_tracy_enabled: bool) -> Self {
+    fn new(
+        is_tracy_enabled: bool,
+        file_worker: Option<FileWorker>,
+        lost: Option<Arc<AtomicU64>>,
+    ) -> Self {
         if is_tracy_enabled {
             std::thread::sleep(TRACY_CONNECTION_TIMEOUT);
         }
-        Self { is_tracy_enabled }
+        Self {
+            is_tracy_enabled,
+            file_worker,
+            lost,
+        }
+    }
+
+    /// Number of log lines dropped by [`Overflow::DropAndCount`]
+    ///
+    /// Always `0` when non-blocking file logging is disabled, or set to [`Overflow::Block`].
+    pub fn lost_lines(&self) -> u64 {
+        self.lost
+            .as_ref()
+            .map_or(0, |lost| lost.load(Ordering::Relaxed))
     }
 }
 
@@ -184,6 +356,11 @@ impl Drop for TracingDefer {
         if self.is_tracy_enabled {
             std::thread::sleep(TRACY_CONNECTION_TIMEOUT);
         }
+
+        if let Some(worker) = self.file_worker.take() {
+            worker.stop.store(true, Ordering::Relaxed);
+            let _ = worker.handle.join();
+        }
     }
 }
 
@@ -224,3 +401,105 @@ impl<W> Clone for Writer<W> {
         Writer(Arc::clone(&self.0))
     }
 }
+
+/// Writer backing [`TracingBuilder::with_file_nonblocking`]
+///
+/// Serializes the formatted event onto a bounded channel for the background writer to pick
+/// up, rather than doing file I/O on the calling thread.
+struct NonBlockingWriter {
+    sender: SyncSender<Vec<u8>>,
+    overflow: Overflow,
+    lost: Arc<AtomicU64>,
+}
+
+impl Write for NonBlockingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.overflow {
+            Overflow::Block => {
+                self.sender.send(buf.to_vec()).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::BrokenPipe, "log writer thread gone")
+                })?;
+            }
+            Overflow::DropAndCount => {
+                if let Err(mpsc::TrySendError::Full(_)) = self.sender.try_send(buf.to_vec()) {
+                    self.lost.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        // The background writer owns flush timing
+        Ok(())
+    }
+}
+
+impl Clone for NonBlockingWriter {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            overflow: self.overflow,
+            lost: Arc::clone(&self.lost),
+        }
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for NonBlockingWriter {
+    type Writer = NonBlockingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Dispatches to either the blocking or non-blocking file writer
+///
+/// Needed so [`TracingBuilder::init`] can build the file layer in one place regardless of
+/// [`FileMode`], rather than two branches returning differently-typed layers.
+enum FileWriter {
+    Blocking(Writer<BufWriter<File>>),
+    NonBlocking(NonBlockingWriter),
+}
+
+impl Clone for FileWriter {
+    fn clone(&self) -> Self {
+        match self {
+            FileWriter::Blocking(wtr) => FileWriter::Blocking(wtr.clone()),
+            FileWriter::NonBlocking(wtr) => FileWriter::NonBlocking(wtr.clone()),
+        }
+    }
+}
+
+enum FileWriterGuard<'a> {
+    Blocking(MutexWriteGuard<'a, BufWriter<File>>),
+    NonBlocking(NonBlockingWriter),
+}
+
+impl Write for FileWriterGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            FileWriterGuard::Blocking(wtr) => wtr.write(buf),
+            FileWriterGuard::NonBlocking(wtr) => wtr.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            FileWriterGuard::Blocking(wtr) => wtr.flush(),
+            FileWriterGuard::NonBlocking(wtr) => wtr.flush(),
+        }
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for FileWriter {
+    type Writer = FileWriterGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        match self {
+            FileWriter::Blocking(wtr) => FileWriterGuard::Blocking(wtr.make_writer()),
+            FileWriter::NonBlocking(wtr) => FileWriterGuard::NonBlocking(wtr.clone()),
+        }
+    }
+}
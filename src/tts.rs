@@ -1,9 +1,19 @@
-//! Small wrapper around the Windows SDK for TTS
+//! Small wrapper around the Windows SDK for TTS and speech recognition
 
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 use windows::core::Error as WindowsError;
+use windows::Foundation::TypedEventHandler;
+use windows::Media::SpeechRecognition::{
+    SpeechContinuousRecognitionResultGeneratedEventArgs, SpeechContinuousRecognitionSession,
+    SpeechRecognitionHypothesisGeneratedEventArgs, SpeechRecognizer,
+};
 use windows::Media::SpeechSynthesis::{SpeechSynthesizer, VoiceInformation};
 use windows::Storage::Streams::DataReader;
 
@@ -82,3 +92,223 @@ impl Drop for Synthesizer {
         let _ = self.inner.Close();
     }
 }
+
+/// A single recognized word with its confidence and position in the audio
+///
+/// `SpeechRecognitionResult` only reports timing and confidence for the phrase as a
+/// whole, not per word, so every [`Word`] of a given result shares the same `confidence`
+/// and `len`; `start` is always [`Duration::ZERO`] for the same reason.
+#[derive(Debug, Clone)]
+pub struct Word {
+    pub text: String,
+    pub confidence: f32,
+    pub start: Duration,
+    pub len: Duration,
+}
+
+/// An event emitted while streaming recognition results
+#[derive(Debug, Clone)]
+pub enum RecognitionEvent {
+    /// An unfinalized, best-effort guess at what has been said so far
+    Partial(String),
+    /// A finalized result along with per-word timing and confidence
+    Final { text: String, words: Vec<Word> },
+}
+
+/// Streaming speech-to-text, the inverse of [`Synthesizer`]
+///
+/// Recognition listens on the system's default microphone. WinRT's speech recognition
+/// API has no supported way to feed it arbitrary in-memory audio (the continuous session
+/// always binds to the default capture device), so unlike [`Synthesizer::say`] there is
+/// no way to round-trip a [`Spoken`] buffer through this type: there is deliberately no
+/// `push`-a-PCM-frame API and no one-shot `recognize(&Spoken)`, only mic-bound
+/// [`recognize_stream`](Self::recognize_stream).
+pub struct Recognizer {
+    inner: SpeechRecognizer,
+    session: SpeechContinuousRecognitionSession,
+    /// State for the in-flight [`RecognitionStream`], if any. The event handlers below are
+    /// registered once in [`new`](Self::new) and just forward into whichever state is
+    /// current, rather than re-registering (and leaking) a new set of handlers per call to
+    /// [`recognize_stream`](Self::recognize_stream).
+    current: Arc<Mutex<Option<Arc<Mutex<StreamState>>>>>,
+}
+
+impl Recognizer {
+    pub fn new() -> Result<Self, WindowsError> {
+        let inner = SpeechRecognizer::new()?;
+        let session = inner.ContinuousRecognitionSession()?;
+        let current: Arc<Mutex<Option<Arc<Mutex<StreamState>>>>> = Arc::new(Mutex::new(None));
+
+        let hypothesis_current = Arc::clone(&current);
+        inner.HypothesisGenerated(&TypedEventHandler::new(
+            move |_, args: &Option<SpeechRecognitionHypothesisGeneratedEventArgs>| {
+                if let Some(args) = args {
+                    if let Some(state) = &*hypothesis_current.lock().expect("lock poisoned") {
+                        let text = args.Hypothesis()?.Text()?.to_string_lossy();
+                        state.push(RecognitionEvent::Partial(text));
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+
+        let result_current = Arc::clone(&current);
+        session.ResultGenerated(&TypedEventHandler::new(
+            move |_, args: &Option<SpeechContinuousRecognitionResultGeneratedEventArgs>| {
+                if let Some(args) = args {
+                    if let Some(state) = &*result_current.lock().expect("lock poisoned") {
+                        let result = args.Result()?;
+                        let text = result.Text()?.to_string_lossy();
+
+                        // Confidence and duration are only reported for the whole phrase
+                        let confidence = result.RawConfidence()? as f32;
+                        let len =
+                            Duration::from_nanos((result.PhraseDuration()?.Duration as u64).saturating_mul(100));
+                        let words = text
+                            .split_whitespace()
+                            .map(|word| Word {
+                                text: word.to_string(),
+                                confidence,
+                                start: Duration::ZERO,
+                                len,
+                            })
+                            .collect();
+
+                        state.push(RecognitionEvent::Final { text, words });
+                    }
+                }
+                Ok(())
+            },
+        ))?;
+
+        let completed_current = Arc::clone(&current);
+        session.Completed(&TypedEventHandler::new(move |_, _| {
+            if let Some(state) = &*completed_current.lock().expect("lock poisoned") {
+                state.finish();
+            }
+            Ok(())
+        }))?;
+
+        Ok(Self {
+            inner,
+            session,
+            current,
+        })
+    }
+
+    /// Stop listening, letting the session finalize any in-flight result
+    pub fn stop(&self) -> Result<(), WindowsError> {
+        self.session.StopAsync()?.get()?;
+        Ok(())
+    }
+
+    /// Start continuous recognition from the default microphone, returning a stream of
+    /// partial and final results
+    ///
+    /// Drop the returned stream (or call [`stop`](Self::stop)) to stop listening. Starting
+    /// a new stream replaces whichever one was previously current.
+    pub fn recognize_stream(&self) -> Result<RecognitionStream, WindowsError> {
+        let state = Arc::new(Mutex::new(StreamState::default()));
+        *self.current.lock().expect("lock poisoned") = Some(Arc::clone(&state));
+
+        self.session.StartAsync()?.get()?;
+
+        Ok(RecognitionStream {
+            state,
+            session: self.session.clone(),
+            current: Arc::clone(&self.current),
+        })
+    }
+}
+
+impl Drop for Recognizer {
+    fn drop(&mut self) {
+        let _ = self.inner.Close();
+    }
+}
+
+#[derive(Default)]
+struct StreamState {
+    queue: VecDeque<RecognitionEvent>,
+    waker: Option<Waker>,
+    done: bool,
+}
+
+trait StreamStateHandle {
+    fn push(&self, event: RecognitionEvent);
+    fn finish(&self);
+}
+
+impl StreamStateHandle for Arc<Mutex<StreamState>> {
+    fn push(&self, event: RecognitionEvent) {
+        let mut state = self.lock().expect("lock poisoned");
+        state.queue.push_back(event);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn finish(&self) {
+        let mut state = self.lock().expect("lock poisoned");
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stream of [`RecognitionEvent`]s produced by [`Recognizer::recognize_stream`]
+///
+/// Dropping the stream stops the continuous recognition session, same as calling
+/// [`Recognizer::stop`], unless a newer stream has since replaced it as the recognizer's
+/// current one (in which case that newer stream owns stopping the session).
+pub struct RecognitionStream {
+    state: Arc<Mutex<StreamState>>,
+    session: SpeechContinuousRecognitionSession,
+    current: Arc<Mutex<Option<Arc<Mutex<StreamState>>>>>,
+}
+
+impl RecognitionStream {
+    pub async fn next(&mut self) -> Option<RecognitionEvent> {
+        std::future::poll_fn(|cx| self.poll_next(cx)).await
+    }
+
+    fn poll_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<RecognitionEvent>> {
+        let mut state = self.state.lock().expect("lock poisoned");
+        if let Some(event) = state.queue.pop_front() {
+            Poll::Ready(Some(event))
+        } else if state.done {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl futures_core::Stream for RecognitionStream {
+    type Item = RecognitionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Self::poll_next(&mut self, cx)
+    }
+}
+
+impl Drop for RecognitionStream {
+    fn drop(&mut self) {
+        let mut current = self.current.lock().expect("lock poisoned");
+        let is_current =
+            matches!(current.as_ref(), Some(active) if Arc::ptr_eq(active, &self.state));
+        if is_current {
+            *current = None;
+        }
+        drop(current);
+
+        if !is_current {
+            return;
+        }
+        if let Ok(op) = self.session.StopAsync() {
+            let _ = op.get();
+        }
+    }
+}